@@ -0,0 +1,127 @@
+//! Generic keyed annotation-file merging for `--annotate`, for joining
+//! additional per-variant resources (VEP consequences, CADD scores, LD
+//! scores, ...) onto the harmonized output table in the same pass as
+//! [`crate::dbsnp_matching`]'s own dbSNP join, instead of requiring a
+//! separate post-processing step per resource.
+//!
+//! Unlike dbSNP matching, this crate has no fixed idea of what an annotation
+//! file's columns are named or how many key columns it joins on -- a VEP
+//! consequence table might key on `chr`/`pos_hg38`/`ref`/`alt`, an LD score
+//! file might key on `rsid` alone. Each `--annotate` spec (parsed by
+//! [`crate::parse_annotation_source`] into an [`AnnotationSource`]) names its
+//! own key and output columns instead.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::{
+    error::{GwasError, Result},
+    field::Field,
+    Data,
+};
+
+/// One `--annotate` source: a keyed TSV (gzip-compressed or plain) to
+/// left-join onto the harmonized output, as configured on the CLI by
+/// `name=...,path=...,keys=...,columns=...` (see
+/// [`crate::parse_annotation_source`]).
+#[derive(Clone, Debug)]
+pub(crate) struct AnnotationSource {
+    /// Identifies this source in error messages -- multiple `--annotate`
+    /// flags can be given, so a bad key/output column needs to say which one.
+    pub(crate) name:           String,
+    pub(crate) path:           String,
+    /// Column names present in both `path` and the harmonized output to join
+    /// on, e.g. `["chr_hg38", "pos_hg38", "ref", "alt"]` or `["rsid"]`.
+    pub(crate) key_columns:    Vec<String>,
+    /// Which of `path`'s non-key columns to carry into the output. `None`
+    /// keeps every column `path` has that isn't one of `key_columns`.
+    pub(crate) output_columns: Option<Vec<String>>,
+}
+
+/// Left-joins every configured `--annotate` source onto `data`, in order.
+/// A source's key columns must already exist in `data`'s header --
+/// typically `chr_hg38`/`pos_hg38`/`ref`/`alt` or `rsid`, since this runs
+/// after dbSNP matching has populated them. A row whose key doesn't match
+/// any row in the source gets that source's output columns filled `NA`, the
+/// same convention [`crate::dbsnp_matching`] uses for an unmatched dbSNP
+/// row.
+pub(crate) fn annotate(mut data: Data, sources: &[AnnotationSource]) -> Result<Data> {
+    for source in sources {
+        data = apply_source(data, source)?;
+    }
+    Ok(data)
+}
+
+/// Opens `path` for reading, transparently gzip-decompressing if it ends in
+/// `.gz` -- the same convention [`crate::dbsnp_vcf::read_dbsnp_vcf`] uses for
+/// an arbitrary local file whose compression isn't guaranteed the way
+/// `--dbsnp-file` is.
+fn open_maybe_gz(path: &str) -> Result<Box<dyn std::io::Read>> {
+    let file = std::fs::File::open(path)?;
+    Ok(if path.ends_with(".gz") {
+        Box::new(flate2::read::GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    })
+}
+
+fn column_idx(data: &Data, column: &str, source_name: &str, source_path: &str) -> Result<usize> {
+    data.idx_opt(column).ok_or_else(|| {
+        GwasError::LegendError(format!(
+            "--annotate {source_name}: column `{column}` not found in {source_path}"
+        ))
+    })
+}
+
+fn apply_source(mut data: Data, source: &AnnotationSource) -> Result<Data> {
+    let data_key_idxs = source
+        .key_columns
+        .iter()
+        .map(|c| column_idx(&data, c, &source.name, "the harmonized output"))
+        .collect::<Result<Vec<usize>>>()?;
+
+    let source_data = Data::read('\t', open_maybe_gz(&source.path)?, true, None);
+    let source_key_idxs = source
+        .key_columns
+        .iter()
+        .map(|c| column_idx(&source_data, c, &source.name, &source.path))
+        .collect::<Result<Vec<usize>>>()?;
+    let output_idxs = match &source.output_columns {
+        Some(cols) => {
+            cols.iter()
+                .map(|c| column_idx(&source_data, c, &source.name, &source.path))
+                .collect::<Result<Vec<usize>>>()?
+        },
+        None => {
+            (0..source_data.header.len())
+                .filter(|i| !source_key_idxs.contains(i))
+                .collect()
+        },
+    };
+    let output_columns: Vec<String> = output_idxs
+        .iter()
+        .map(|&i| source_data.header[i].clone())
+        .collect();
+
+    // ahash instead of the default SipHash, same rationale as
+    // `dbsnp_matching`'s join map: probed once per output row, and DoS
+    // resistance doesn't matter for keys read from a file the caller
+    // supplied themselves.
+    let join: HashMap<Vec<&str>, &Vec<Field>, ahash::RandomState> = HashMap::from_par_iter(
+        source_data
+            .data
+            .par_iter()
+            .map(|r| (source_key_idxs.iter().map(|&i| r[i].as_str()).collect(), r)),
+    );
+
+    data.header.extend(output_columns);
+    data.data.par_iter_mut().for_each(|r| {
+        let key: Vec<&str> = data_key_idxs.iter().map(|&i| r[i].as_str()).collect();
+        match join.get(&key) {
+            Some(row) => r.extend(output_idxs.iter().map(|&i| row[i].clone())),
+            None => r.extend(output_idxs.iter().map(|_| Field::from("NA"))),
+        }
+    });
+    Ok(data)
+}
@@ -0,0 +1,106 @@
+//! An async front-end for embedding the pipeline in a service (e.g. a web
+//! handler that harmonizes uploaded sumstats on demand) without blocking a
+//! tokio worker thread. Every stage is still the same synchronous,
+//! rayon-parallel code; this module only moves it onto `spawn_blocking`.
+
+use crate::{
+    check_effect_allele_orientation,
+    check_gnomad_concordance,
+    check_pvalue_consistency,
+    error::{GwasError, Result},
+    fill_missing_eaf_from_gnomad,
+    legend::LegendSource,
+    liftover,
+    preformat,
+    ref_alt_check,
+    resolve_mhc_region,
+    resolve_palindromic_snps,
+    Ctx,
+    Data,
+};
+
+fn join_error(e: tokio::task::JoinError) -> GwasError {
+    GwasError::ExternalToolError {
+        tool:    "tokio blocking task".to_string(),
+        message: e.to_string(),
+    }
+}
+
+/// Fetch the legend off the tokio runtime's worker threads.
+pub async fn fetch_legend_async(source: Box<dyn LegendSource + Send>) -> Result<Data> {
+    tokio::task::spawn_blocking(move || source.fetch())
+        .await
+        .map_err(join_error)?
+}
+
+/// Run the full harmonization pipeline without blocking the calling task,
+/// returning the final harmonized table.
+pub async fn run_pipeline_async(ctx: Ctx) -> Result<Data> {
+    let ctx = std::sync::Arc::new(ctx);
+
+    let preformat_ctx = ctx.clone();
+    let raw_data = tokio::task::spawn_blocking(move || preformat(&preformat_ctx, None, None))
+        .await
+        .map_err(join_error)??;
+
+    let liftover_ctx = ctx.clone();
+    let liftover_data = raw_data.clone();
+    tokio::task::spawn_blocking(move || liftover(&liftover_ctx, &liftover_data, false, None))
+        .await
+        .map_err(join_error)??;
+
+    let match_ctx = ctx.clone();
+    let (raw_data_merged, raw_data_missing) = tokio::task::spawn_blocking(move || {
+        let matcher = match_ctx.args.variant_matcher.build();
+        matcher.match_variants(&match_ctx, raw_data)
+    })
+    .await
+    .map_err(join_error)??;
+
+    let fill_eaf_ctx = ctx.clone();
+    let raw_data_merged = tokio::task::spawn_blocking(move || {
+        fill_missing_eaf_from_gnomad(&fill_eaf_ctx, raw_data_merged)
+    })
+    .await
+    .map_err(join_error)??;
+
+    let orientation_ctx = ctx.clone();
+    let raw_data_merged = tokio::task::spawn_blocking(move || {
+        check_effect_allele_orientation(&orientation_ctx, raw_data_merged)
+    })
+    .await
+    .map_err(join_error)??;
+
+    let palindromic_ctx = ctx.clone();
+    let raw_data_merged = tokio::task::spawn_blocking(move || {
+        resolve_palindromic_snps(&palindromic_ctx, raw_data_merged)
+    })
+    .await
+    .map_err(join_error)??;
+
+    let concordance_ctx = ctx.clone();
+    let raw_data_merged = tokio::task::spawn_blocking(move || {
+        check_gnomad_concordance(&concordance_ctx, raw_data_merged)
+    })
+    .await
+    .map_err(join_error)??;
+
+    let pvalue_ctx = ctx.clone();
+    let raw_data_merged =
+        tokio::task::spawn_blocking(move || check_pvalue_consistency(&pvalue_ctx, raw_data_merged))
+            .await
+            .map_err(join_error)??;
+
+    let mhc_ctx = ctx.clone();
+    let raw_data_merged =
+        tokio::task::spawn_blocking(move || resolve_mhc_region(&mhc_ctx, raw_data_merged))
+            .await
+            .map_err(join_error)??;
+
+    let refcheck_ctx = ctx.clone();
+    tokio::task::spawn_blocking(move || {
+        ref_alt_check(&refcheck_ctx, raw_data_merged, raw_data_missing, None)
+    })
+    .await
+    .map_err(join_error)?
+}
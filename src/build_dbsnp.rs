@@ -0,0 +1,240 @@
+//! Builds this crate's bespoke dbSNP resource (`chr`, `pos_hg19`, `pos_hg38`,
+//! `ref`, `alt`, `rsid`, and gnomAD ancestry AF columns -- the layout
+//! documented at the top of [`crate::dbsnp_index`]) from public downloads,
+//! driven by the standalone `build-dbsnp` subcommand. Assembling this file by
+//! hand has so far been undocumented tribal knowledge; this gives it one
+//! authoritative path.
+//!
+//! Doesn't fetch anything itself -- `--dbsnp-vcf`, `--chain-file`, and
+//! `--gnomad-af-tsv` are all local paths, the same convention `--fasta-ref`
+//! and `--chain-file` already use elsewhere in this crate. Source the dbSNP
+//! VCF from NCBI and the chain file from UCSC the same way `liftover`'s own
+//! docs point at; extract a flat gnomAD AF TSV with `bcftools query`:
+//!
+//! ```text
+//! bcftools query -f '%CHROM\t%POS\t%REF\t%ALT\t%INFO/AF_nfe\t%INFO/AF_afr\n' gnomad.vcf.bgz \
+//!     > gnomad_af.tsv
+//! ```
+//!
+//! gnomAD's VCF `INFO` field carries dozens of per-ancestry `AF_*`
+//! subfields, a large surface better extracted with a purpose-built tool
+//! than reimplemented here.
+
+use std::{collections::HashMap, path::Path};
+
+use rayon::prelude::*;
+
+use crate::{
+    dbsnp_vcf,
+    error::{GwasError, Result},
+    export::GenomeBuild,
+    field::Field,
+    liftover_chain,
+    resolve_chunk_rows,
+    Data,
+};
+
+fn other_build(build: &GenomeBuild) -> GenomeBuild {
+    match build {
+        GenomeBuild::Hg19 => GenomeBuild::Hg38,
+        GenomeBuild::Hg38 => GenomeBuild::Hg19,
+    }
+}
+
+/// Writes `chr`/`pos` pairs out as BED6, one row per `data` row in order,
+/// using the row's own index (not [`crate::format_bed_rows_parallel`]'s
+/// `line + 2` convention, which exists only to thread through that
+/// function's header-row and 1-indexing quirks) as the name column, so
+/// [`read_lifted_bed`] can match lifted rows straight back to their source
+/// without carrying any of that baggage.
+fn write_bed(
+    data: &Data,
+    chr_idx: usize,
+    pos_idx: usize,
+    ref_idx: usize,
+    path: &Path,
+) -> Result<()> {
+    let mut buf = String::new();
+    for (i, r) in data.data.iter().enumerate() {
+        let pos = r[pos_idx].parse::<i64>().map_err(|e| {
+            GwasError::InputParseError {
+                line:    i + 1,
+                col:     pos_idx,
+                message: e.to_string(),
+            }
+        })?;
+        let ref_len = if r[ref_idx] == "-" {
+            1
+        } else {
+            r[ref_idx].len().max(1) as i64
+        };
+        buf.push_str(&format!(
+            "chr{}\t{}\t{}\t{}\t0\t+\n",
+            r[chr_idx],
+            pos - 1,
+            pos - 1 + ref_len,
+            i
+        ));
+    }
+    std::fs::write(path, buf)?;
+    Ok(())
+}
+
+/// Reads a lifted BED back into a map from source row index to the lifted
+/// `(chrom, 1-based pos)`, stripping the `chr` prefix [`write_bed`] added --
+/// the same convention [`crate::write_build_bed`] uses -- so it compares
+/// directly against this crate's own unprefixed `chr` column. A row that
+/// landed on a different chromosome than it started on doesn't fit this
+/// resource's single shared `chr` column, so it's treated the same as an
+/// unmapped row rather than silently attributed to the wrong chromosome.
+fn read_lifted_bed(path: &Path, source_chroms: &[&str]) -> Result<HashMap<usize, u64>> {
+    let mut lifted = HashMap::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (Some(chrom), Some(start), Some(name)) = (fields.first(), fields.get(1), fields.get(3))
+        else {
+            continue;
+        };
+        let (Ok(start), Ok(row)) = (start.parse::<u64>(), name.parse::<usize>()) else {
+            continue;
+        };
+        let chrom = chrom.strip_prefix("chr").unwrap_or(chrom);
+        if source_chroms.get(row) != Some(&chrom) {
+            continue;
+        }
+        lifted.insert(row, start + 1);
+    }
+    Ok(lifted)
+}
+
+/// Derives `data`'s `pos_{other_build}` column via `chain_file`, by
+/// round-tripping `data`'s `chr`/`pos_{build}`/`ref` columns through a BED
+/// file and [`liftover_chain::native_liftover`] -- the same native Rust
+/// lifter `liftover` uses by default, reused here instead of a second
+/// implementation.
+fn lift_positions(
+    data: &Data,
+    build: &GenomeBuild,
+    chain_file: &Path,
+    work_dir: &Path,
+) -> Result<Vec<Field>> {
+    let chr_idx = data.idx("chr");
+    let pos_idx = data.idx(&format!("pos_{}", build.name()));
+    let ref_idx = data.idx("ref");
+
+    let input_bed = work_dir.join("input.bed");
+    let output_bed = work_dir.join("output.bed");
+    let unmapped_bed = work_dir.join("unmapped.bed");
+    write_bed(data, chr_idx, pos_idx, ref_idx, &input_bed)?;
+    liftover_chain::native_liftover(chain_file, &input_bed, &output_bed, &unmapped_bed)?;
+
+    let source_chroms: Vec<&str> = data.data.iter().map(|r| r[chr_idx].as_str()).collect();
+    let lifted = read_lifted_bed(&output_bed, &source_chroms)?;
+    Ok((0..data.data.len())
+        .map(|i| {
+            match lifted.get(&i) {
+                Some(pos) => pos.to_string().into(),
+                None => "NA".into(),
+            }
+        })
+        .collect())
+}
+
+/// A `(chr, pos, ref, alt)`-keyed join map of a dbSNP/gnomAD row's extra
+/// columns, as built by [`read_gnomad_af_tsv`].
+type AfJoinMap = HashMap<(String, String, String, String), Vec<Field>>;
+
+/// Reads a `--gnomad-af-tsv` extract (`chr`, `pos`, `ref`, `alt`, then
+/// arbitrary ancestry AF columns, in `bcftools query`'s column order) into a
+/// `(chr, pos, ref, alt)`-keyed map of its AF columns, plus the AF column
+/// names themselves.
+fn read_gnomad_af_tsv(path: &Path) -> Result<(Vec<String>, AfJoinMap)> {
+    let file = std::fs::File::open(path)?;
+    let af = Data::read('\t', file, true, None);
+    let chr_idx = af.idx("chr");
+    let pos_idx = af.idx("pos");
+    let ref_idx = af.idx("ref");
+    let alt_idx = af.idx("alt");
+    let af_col_idxs: Vec<usize> = (0..af.header.len())
+        .filter(|i| ![chr_idx, pos_idx, ref_idx, alt_idx].contains(i))
+        .collect();
+    let af_cols: Vec<String> = af_col_idxs.iter().map(|&i| af.header[i].clone()).collect();
+    let map = af
+        .data
+        .par_iter()
+        .map(|r| {
+            (
+                (
+                    r[chr_idx].to_string(),
+                    r[pos_idx].to_string(),
+                    r[ref_idx].to_string(),
+                    r[alt_idx].to_string(),
+                ),
+                af_col_idxs.iter().map(|&i| r[i].clone()).collect(),
+            )
+        })
+        .collect();
+    Ok((af_cols, map))
+}
+
+/// Builds the dbSNP resource at `output` from `dbsnp_vcf` (the official
+/// dbSNP VCF release, whose positions are on `build`), optionally filling in
+/// `pos_{other build}` via `chain_file` and gnomAD ancestry AFs via
+/// `gnomad_af_tsv`. Either is left `"NA"`/omitted when not given, rather
+/// than refusing to build a resource at all -- a resource with only one
+/// build's positions (e.g. for [`crate::VariantMatcherKind::Rsid`]) is still
+/// useful.
+pub(crate) fn build(
+    dbsnp_vcf: &Path,
+    build: &GenomeBuild,
+    chain_file: Option<&Path>,
+    gnomad_af_tsv: Option<&Path>,
+    output: &Path,
+) -> Result<()> {
+    let mut data = dbsnp_vcf::read_dbsnp_vcf(dbsnp_vcf, build)?;
+    let other = other_build(build);
+    let other_pos_col = format!("pos_{}", other.name());
+
+    let other_positions = match chain_file {
+        Some(chain_file) => {
+            let work_dir = tempfile::Builder::new()
+                .prefix("gwas-summary-stats-build-dbsnp-")
+                .tempdir()?;
+            lift_positions(&data, build, chain_file, work_dir.path())?
+        },
+        None => vec!["NA".into(); data.data.len()],
+    };
+    data.header.push(other_pos_col);
+    for (r, pos) in data.data.iter_mut().zip(other_positions) {
+        r.push(pos);
+    }
+
+    let mut column_order = vec!["chr", "pos_hg19", "pos_hg38", "ref", "alt", "rsid"];
+    let af_cols = if let Some(gnomad_af_tsv) = gnomad_af_tsv {
+        let chr_idx = data.idx("chr");
+        let pos_idx = data.idx(&format!("pos_{}", build.name()));
+        let ref_idx = data.idx("ref");
+        let alt_idx = data.idx("alt");
+        let (af_cols, af_map) = read_gnomad_af_tsv(gnomad_af_tsv)?;
+        for r in data.data.iter_mut() {
+            let key = (
+                r[chr_idx].to_string(),
+                r[pos_idx].to_string(),
+                r[ref_idx].to_string(),
+                r[alt_idx].to_string(),
+            );
+            match af_map.get(&key) {
+                Some(values) => r.extend(values.iter().cloned()),
+                None => r.extend(af_cols.iter().map(|_| Field::from("NA"))),
+            }
+        }
+        data.header.extend(af_cols.iter().cloned());
+        af_cols
+    } else {
+        Vec::new()
+    };
+    column_order.extend(af_cols.iter().map(String::as_str));
+    data.reorder(&column_order);
+
+    data.write(output, None, resolve_chunk_rows(None))
+}
@@ -0,0 +1,273 @@
+//! A compact, memory-mappable on-disk index over the dbSNP resource, keyed
+//! by `(chr, pos_hg19)`, so repeated per-trait runs can skip re-parsing the
+//! multi-gigabyte reference file. Built once by the `build-index`
+//! subcommand; [`crate::dbsnp_matching`] queries it via [`DbsnpIndex::open`]
+//! when `--dbsnp-index` is given instead of building its usual in-memory
+//! `HashMap` over the raw file.
+//!
+//! # On-disk layout
+//!
+//! ```text
+//! magic:          8 bytes, b"GWDBIDX1"
+//! extra_columns:  u32 count, then for each: u16 len + utf8 bytes
+//!                 (every dbSNP column except chr/pos_hg19/pos_hg38, in the
+//!                 resource's original order)
+//! record_count:   u64
+//! key table:      record_count fixed-size `KeyEntry`s (see below), sorted
+//!                 ascending by (chr, pos_hg19)
+//! blob section:   each key entry's `ref`, `alt`, and every extra column,
+//!                 tab-joined and utf8-encoded back to back
+//! ```
+//!
+//! Each `KeyEntry` is 32 bytes: an 8-byte zero-padded ASCII chromosome
+//! label, a `u32` `pos_hg19`, a `u32` `pos_hg38`, a `u64` offset into the
+//! blob section, and a `u32` blob length, with 4 bytes of padding to keep
+//! the record a round size. Multiple entries may share a `(chr, pos_hg19)`
+//! key (multi-allelic sites); [`DbsnpIndex::lookup`] returns every row in
+//! that bucket so callers can pick the one whose `ref`/`alt` matches.
+
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{
+    error::{GwasError, Result},
+    Data,
+};
+
+const MAGIC: &[u8; 8] = b"GWDBIDX1";
+const CHR_LABEL_LEN: usize = 8;
+const KEY_ENTRY_LEN: usize = 32;
+
+/// A single dbSNP row read back out of a [`DbsnpIndex`] bucket: `ref`,
+/// `alt`, and the remaining extra columns, in the index's `extra_columns`
+/// order (which always starts with `ref`, `alt`), plus the `pos_hg38` its
+/// `KeyEntry` carries alongside the `(chr, pos_hg19)` the bucket was looked
+/// up by -- callers need it to enforce the same `pos_hg38` agreement
+/// [`crate::dbsnp_matching`]'s in-memory join key requires.
+pub(crate) struct IndexedRow<'a> {
+    pub columns:  Vec<&'a str>,
+    pub pos_hg38: u32,
+}
+
+fn pack_chr_label(chr: &str) -> Result<[u8; CHR_LABEL_LEN]> {
+    if chr.len() > CHR_LABEL_LEN {
+        return Err(GwasError::InputParseError {
+            line:    0,
+            col:     0,
+            message: format!(
+                "dbSNP chromosome label `{chr}` is longer than the {CHR_LABEL_LEN} bytes the \
+                 on-disk index reserves for it"
+            ),
+        });
+    }
+    let mut label = [0u8; CHR_LABEL_LEN];
+    label[..chr.len()].copy_from_slice(chr.as_bytes());
+    Ok(label)
+}
+
+/// Read the dbSNP resource, sort it by `(chr, pos_hg19)`, and write it out
+/// to `output` in the format documented at the top of this module.
+pub(crate) fn build_index(dbsnp_file: &Path, output: &Path) -> Result<()> {
+    let file = flate2::read::GzDecoder::new(std::fs::File::open(dbsnp_file)?);
+    let dbsnp = Data::read('\t', file, true, None);
+    let chr_idx = dbsnp.idx("chr");
+    let pos19_idx = dbsnp.idx("pos_hg19");
+    let pos38_idx = dbsnp.idx("pos_hg38");
+    let ref_idx = dbsnp.idx("ref");
+    let alt_idx = dbsnp.idx("alt");
+
+    let extra_idxs: Vec<usize> = std::iter::once(ref_idx)
+        .chain(std::iter::once(alt_idx))
+        .chain(
+            (0..dbsnp.header.len())
+                .filter(|&i| ![chr_idx, pos19_idx, pos38_idx, ref_idx, alt_idx].contains(&i)),
+        )
+        .collect();
+    let extra_columns: Vec<String> = extra_idxs
+        .iter()
+        .map(|&i| dbsnp.header[i].clone())
+        .collect();
+
+    let mut rows = dbsnp.data;
+    rows.sort_by(|a, b| {
+        let a_pos = a[pos19_idx].parse::<u32>().unwrap_or(u32::MAX);
+        let b_pos = b[pos19_idx].parse::<u32>().unwrap_or(u32::MAX);
+        (a[chr_idx].as_str(), a_pos).cmp(&(b[chr_idx].as_str(), b_pos))
+    });
+
+    let mut keys = Vec::with_capacity(rows.len() * KEY_ENTRY_LEN);
+    let mut blob = Vec::new();
+    for row in &rows {
+        let chr_label = pack_chr_label(&row[chr_idx])?;
+        let pos_hg19: u32 = row[pos19_idx].parse().map_err(|_| {
+            GwasError::InputParseError {
+                line:    0,
+                col:     pos19_idx,
+                message: format!("invalid pos_hg19 `{}` in dbSNP resource", row[pos19_idx]),
+            }
+        })?;
+        let pos_hg38: u32 = row[pos38_idx].parse().unwrap_or(0);
+        let value = extra_idxs
+            .iter()
+            .map(|&i| row[i].as_str())
+            .collect::<Vec<_>>()
+            .join("\t");
+        let blob_offset = blob.len() as u64;
+        blob.extend_from_slice(value.as_bytes());
+        let blob_len = value.len() as u32;
+
+        keys.extend_from_slice(&chr_label);
+        keys.extend_from_slice(&pos_hg19.to_le_bytes());
+        keys.extend_from_slice(&pos_hg38.to_le_bytes());
+        keys.extend_from_slice(&blob_offset.to_le_bytes());
+        keys.extend_from_slice(&blob_len.to_le_bytes());
+        keys.extend_from_slice(&[0u8; 4]);
+    }
+
+    let mut out = Vec::with_capacity(MAGIC.len() + 4 + keys.len() + blob.len() + 64);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(extra_columns.len() as u32).to_le_bytes());
+    for name in &extra_columns {
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+    }
+    out.extend_from_slice(&(rows.len() as u64).to_le_bytes());
+    out.extend_from_slice(&keys);
+    out.extend_from_slice(&blob);
+
+    std::fs::write(output, out)?;
+    Ok(())
+}
+
+/// An mmap-backed handle onto an index built by [`build_index`].
+pub(crate) struct DbsnpIndex {
+    mmap:            Mmap,
+    extra_columns:   Vec<String>,
+    key_table_start: usize,
+    blob_start:      usize,
+    record_count:    usize,
+}
+
+impl DbsnpIndex {
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the index file is only ever written atomically by
+        // `build_index` and is not expected to be concurrently truncated or
+        // mutated while a run holds it open, matching the same "trusted
+        // local file" assumption the rest of this pipeline makes about its
+        // other resource files.
+        let mmap = unsafe { Mmap::map(&file)? };
+        if mmap.len() < MAGIC.len() || &mmap[..MAGIC.len()] != MAGIC {
+            return Err(GwasError::InputParseError {
+                line:    0,
+                col:     0,
+                message: format!("{} is not a dbSNP index file", path.display()),
+            });
+        }
+        let mut offset = MAGIC.len();
+        let extra_count = u32::from_le_bytes(mmap[offset..offset + 4].try_into().unwrap()) as usize;
+        offset += 4;
+        let mut extra_columns = Vec::with_capacity(extra_count);
+        for _ in 0..extra_count {
+            let len = u16::from_le_bytes(mmap[offset..offset + 2].try_into().unwrap()) as usize;
+            offset += 2;
+            extra_columns.push(String::from_utf8_lossy(&mmap[offset..offset + len]).into_owned());
+            offset += len;
+        }
+        let record_count =
+            u64::from_le_bytes(mmap[offset..offset + 8].try_into().unwrap()) as usize;
+        offset += 8;
+        let key_table_start = offset;
+        let blob_start = key_table_start + record_count * KEY_ENTRY_LEN;
+        Ok(Self {
+            mmap,
+            extra_columns,
+            key_table_start,
+            blob_start,
+            record_count,
+        })
+    }
+
+    /// Column names [`IndexedRow::columns`] lines up with, in order.
+    pub(crate) fn extra_columns(&self) -> &[String] {
+        &self.extra_columns
+    }
+
+    fn key_entry(&self, i: usize) -> (&[u8], u32, u32, u64, u32) {
+        let start = self.key_table_start + i * KEY_ENTRY_LEN;
+        let entry = &self.mmap[start..start + KEY_ENTRY_LEN];
+        let chr_label = &entry[0..CHR_LABEL_LEN];
+        let chr_len = chr_label
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(CHR_LABEL_LEN);
+        let pos_hg19 = u32::from_le_bytes(entry[8..12].try_into().unwrap());
+        let pos_hg38 = u32::from_le_bytes(entry[12..16].try_into().unwrap());
+        let blob_offset = u64::from_le_bytes(entry[16..24].try_into().unwrap());
+        let blob_len = u32::from_le_bytes(entry[24..28].try_into().unwrap());
+        (
+            &chr_label[..chr_len],
+            pos_hg19,
+            pos_hg38,
+            blob_offset,
+            blob_len,
+        )
+    }
+
+    /// Every indexed row sharing `(chr, pos_hg19)`, for the caller to pick
+    /// the one whose `ref`/`alt` matches (exact or flipped).
+    pub(crate) fn lookup(&self, chr: &str, pos_hg19: u32) -> Vec<IndexedRow<'_>> {
+        if self.record_count == 0 {
+            return Vec::new();
+        }
+        let chr = chr.as_bytes();
+        let key = (chr, pos_hg19);
+        let mut lo = 0usize;
+        let mut hi = self.record_count;
+        let mut found = None;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (mid_chr, mid_pos, ..) = self.key_entry(mid);
+            match (mid_chr, mid_pos).cmp(&key) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    found = Some(mid);
+                    break;
+                },
+            }
+        }
+        let Some(hit) = found else {
+            return Vec::new();
+        };
+        let mut start = hit;
+        while start > 0 {
+            let (c, p, ..) = self.key_entry(start - 1);
+            if (c, p) != key {
+                break;
+            }
+            start -= 1;
+        }
+        let mut rows = Vec::new();
+        let mut i = start;
+        loop {
+            let (c, p, pos_hg38, blob_offset, blob_len) = self.key_entry(i);
+            if (c, p) != key {
+                break;
+            }
+            let blob_start = self.blob_start + blob_offset as usize;
+            let blob = &self.mmap[blob_start..blob_start + blob_len as usize];
+            let value = std::str::from_utf8(blob).unwrap_or("");
+            rows.push(IndexedRow {
+                columns: value.split('\t').collect(),
+                pos_hg38,
+            });
+            i += 1;
+            if i >= self.record_count {
+                break;
+            }
+        }
+        rows
+    }
+}
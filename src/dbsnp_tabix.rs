@@ -0,0 +1,117 @@
+//! Queries a bgzip-compressed, tabix-indexed dbSNP resource by chromosome
+//! region, for [`crate::VariantMatcherKind::TabixRegion`], instead of
+//! reading the whole (potentially multi-gigabyte) resource into memory the
+//! way [`crate::dbsnp_matching`]'s default loading path does. Harmonizing a
+//! small targeted sumstats file (Immunochip, Metabochip) only ever touches a
+//! handful of chromosomes' worth of dbSNP rows, so loading the rest is pure
+//! waste.
+//!
+//! Build the companion index once, the same way samtools documents for any
+//! coordinate-sorted TSV:
+//!
+//! ```text
+//! tabix -s 1 -b 2 -e 2 -S 1 dbsnp.tsv.gz
+//! ```
+//!
+//! (column 1 is `chr`, column 2 is `pos_hg19` -- both the start and end of a
+//! single-base record -- and `-S 1` skips the header line, which this
+//! crate's dbSNP TSV doesn't prefix with `#`.)
+
+use std::{collections::HashMap, io::BufRead, path::PathBuf};
+
+use noodles_core::Region;
+
+use crate::{
+    error::{GwasError, Result},
+    field::Field,
+    Data,
+};
+
+/// The standard tabix companion index path convention: `{dbsnp_file}.tbi`.
+fn tabix_index_path(dbsnp_file: &str) -> PathBuf {
+    PathBuf::from(format!("{dbsnp_file}.tbi"))
+}
+
+/// Reads just `dbsnp_file`'s own header line -- the column names
+/// [`crate::dbsnp_matching`] expects (`chr`, `pos_hg19`, `pos_hg38`, `ref`,
+/// `alt`, `rsid`, and gnomAD ancestry AF columns) -- without decompressing
+/// the rest of the file, to label the rows [`load_region_restricted`]'s
+/// region queries return.
+fn read_header(dbsnp_file: &str) -> Result<Vec<String>> {
+    let file = std::fs::File::open(dbsnp_file)?;
+    let mut reader = noodles_bgzf::io::Reader::new(file);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let line = line.trim_end_matches(['\n', '\r']);
+    if line.is_empty() {
+        return Err(GwasError::InputParseError {
+            line:    1,
+            col:     0,
+            message: format!("{dbsnp_file} is empty"),
+        });
+    }
+    Ok(line.split('\t').map(str::to_string).collect())
+}
+
+/// Loads only the dbSNP rows whose `chr`/`pos_hg19` falls within a
+/// chromosome range `raw_data` actually covers, via `dbsnp_file`'s tabix
+/// index. One region query per chromosome present in `raw_data`, spanning
+/// that chromosome's full `[min, max]` `pos_hg19` -- tabix has no cheaper
+/// granularity than a contiguous range, and a sumstats file is already close
+/// to dense across the span of a chromosome it covers at all.
+pub(crate) fn load_region_restricted(dbsnp_file: &str, raw_data: &Data) -> Result<Data> {
+    let tabix_path = tabix_index_path(dbsnp_file);
+    if !tabix_path.is_file() {
+        return Err(GwasError::MissingFile(format!(
+            "{} (tabix index for --dbsnp-file; build one with `tabix -s 1 -b 2 -e 2 -S 1 {}`)",
+            tabix_path.display(),
+            dbsnp_file
+        )));
+    }
+    let index = noodles_tabix::fs::read(&tabix_path)?;
+    let header = read_header(dbsnp_file)?;
+
+    let chr_hg19_idx = raw_data.idx("chr_hg19");
+    let pos_hg19_idx = raw_data.idx("pos_hg19");
+    let mut ranges: HashMap<&str, (u64, u64)> = HashMap::new();
+    for row in &raw_data.data {
+        let Ok(pos) = row[pos_hg19_idx].parse::<u64>() else {
+            continue;
+        };
+        ranges
+            .entry(row[chr_hg19_idx].as_str())
+            .and_modify(|(lo, hi)| {
+                *lo = (*lo).min(pos);
+                *hi = (*hi).max(pos);
+            })
+            .or_insert((pos, pos));
+    }
+
+    let file = std::fs::File::open(dbsnp_file)?;
+    let mut reader = noodles_csi::io::IndexedReader::new(file, index);
+    let mut rows = Vec::new();
+    for (chrom, (lo, hi)) in ranges {
+        let region = format!("{chrom}:{lo}-{hi}")
+            .parse::<Region>()
+            .map_err(|e| {
+                GwasError::InputParseError {
+                    line:    0,
+                    col:     0,
+                    message: format!("invalid region `{chrom}:{lo}-{hi}`: {e}"),
+                }
+            })?;
+        let query = match reader.query(&region) {
+            Ok(query) => query,
+            // Not every chromosome `raw_data` covers is necessarily present
+            // in the dbSNP resource's tabix header (e.g. a contig naming
+            // mismatch) -- treated the same as a region with zero matches,
+            // not a hard error.
+            Err(_) => continue,
+        };
+        for record in query {
+            let record = record?;
+            rows.push(record.as_ref().split('\t').map(Field::from).collect());
+        }
+    }
+    Ok(Data::from_header_and_rows(header, rows))
+}
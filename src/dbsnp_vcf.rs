@@ -0,0 +1,99 @@
+//! Reads the official dbSNP VCF release directly as `--dbsnp-file`, instead
+//! of requiring this crate's bespoke preprocessed TSV (`chr`, `pos_hg19`,
+//! `pos_hg38`, `ref`, `alt`, `rsid`, and gnomAD ancestry AF columns).
+//!
+//! A dbSNP VCF only ever reports one genome build's coordinates -- NCBI
+//! publishes a separate release per assembly rather than one file with both
+//! -- and carries no gnomAD allele-frequency annotation at all. Both are
+//! columns [`crate::DBSNP_MATCHED_COLUMN_ORDER`] expects the bespoke TSV to
+//! supply, so a VCF source can only stand in for the rsID/chr/pos/ref/alt
+//! columns, never the full resource. That's exactly what
+//! [`crate::VariantMatcherKind::Rsid`] joins on, so it's the only matcher a
+//! VCF source works with; `exact-flipped`/`streaming-sorted-merge` key on
+//! `(chr, pos_hg19, ref, alt, pos_hg38)` together (see
+//! [`crate::VariantMatcherKind::needs_both_builds`]) and refuse a VCF source
+//! outright rather than silently building a join that can never match hg19
+//! and hg38 positions from the same row.
+
+use std::{io::BufRead, path::Path};
+
+use crate::{
+    error::{GwasError, Result},
+    export::GenomeBuild,
+    field::Field,
+    Data,
+};
+
+/// Whether `dbsnp_file` looks like the official dbSNP VCF release rather than
+/// this crate's bespoke preprocessed TSV, by extension -- the same
+/// extension-sniffing convention `preformat` already uses to tell a raw
+/// input's format apart.
+pub(crate) fn is_dbsnp_vcf(dbsnp_file: &str) -> bool {
+    dbsnp_file.ends_with(".vcf.gz") || dbsnp_file.ends_with(".vcf")
+}
+
+/// Reads the dbSNP VCF at `path` (gzip-compressed or plain) into a [`Data`]
+/// table with `rsid`, `chr`, `pos_{build}`, `ref`, `alt` columns -- the
+/// subset of [`crate::RsidMatcher`]'s join a VCF can actually supply. `build`
+/// is the genome build `path`'s own `POS` column reports; dbSNP's VCF header
+/// doesn't name it in a form this crate parses, so the caller has to know
+/// which release it downloaded (see `--dbsnp-vcf-build`).
+///
+/// Skips records whose `ID` is `.` (no rsID assigned yet) -- [`RsidMatcher`]
+/// joins on rsID alone and has nothing useful to do with such a row -- and
+/// takes only the first `ALT` allele at a multiallelic site, since a VCF row
+/// carries every alternate allele reported at a position together rather
+/// than one row per allele the way the bespoke TSV does.
+///
+/// [`RsidMatcher`]: crate::RsidMatcher
+pub(crate) fn read_dbsnp_vcf(path: &Path, build: &GenomeBuild) -> Result<Data> {
+    let file = std::fs::File::open(path)?;
+    let reader: Box<dyn std::io::Read> = if path.to_string_lossy().ends_with(".gz") {
+        Box::new(flate2::read::GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    let reader = std::io::BufReader::new(reader);
+
+    let header = vec![
+        "rsid".to_string(),
+        "chr".to_string(),
+        format!("pos_{}", build.name()),
+        "ref".to_string(),
+        "alt".to_string(),
+    ];
+    let mut data = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (Some(chrom), Some(pos), Some(id), Some(reference), Some(alt)) = (
+            fields.first(),
+            fields.get(1),
+            fields.get(2),
+            fields.get(3),
+            fields.get(4),
+        ) else {
+            return Err(GwasError::InputParseError {
+                line:    i + 1,
+                col:     0,
+                message: format!("malformed dbSNP VCF record: `{line}`"),
+            });
+        };
+        if *id == "." {
+            continue;
+        }
+        let chrom = chrom.strip_prefix("chr").unwrap_or(chrom);
+        let alt = alt.split(',').next().unwrap_or(alt);
+        data.push(vec![
+            Field::Owned((*id).to_string()),
+            Field::Owned(chrom.to_string()),
+            Field::Owned((*pos).to_string()),
+            Field::Owned((*reference).to_string()),
+            Field::Owned(alt.to_string()),
+        ]);
+    }
+    Ok(Data::from_header_and_rows(header, data))
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+
+/// Errors produced by the harmonization pipeline.
+///
+/// Every stage returns a [`Result`] built on this type instead of panicking,
+/// so the CLI can print an actionable message and exit with a code that
+/// reflects the failure class.
+#[derive(Debug)]
+pub enum GwasError {
+    /// The legend (Google Sheets row) could not be found or failed
+    /// validation.
+    LegendError(String),
+    /// A raw input row could not be parsed.
+    InputParseError {
+        line:    usize,
+        col:     usize,
+        message: String,
+    },
+    /// The liftover stage failed, including the `liftOver` tool itself
+    /// failing to run or exiting non-zero.
+    LiftoverError(String),
+    /// The reference FASTA could not be indexed or a ref/alt lookup against
+    /// it failed.
+    FastaError(String),
+    /// An input or resource file the pipeline expected to read does not
+    /// exist, so the failure is a missing-file problem rather than a
+    /// malformed-content one.
+    MissingFile(String),
+    /// A stage produced zero rows where at least one was expected (e.g. the
+    /// dbSNP match or final harmonized table came back empty), which almost
+    /// always means a filter or matcher misconfiguration rather than a
+    /// genuinely empty input.
+    EmptyResult(String),
+    /// An external tool other than `liftOver` (e.g. a tokio blocking task)
+    /// failed to run.
+    ExternalToolError { tool: String, message: String },
+    /// The rayon global thread pool could not be configured from
+    /// `--threads`.
+    ThreadPoolError(String),
+    /// An I/O error.
+    Io(std::io::Error),
+    /// An HTTP request to the Google Sheets API failed.
+    Http(reqwest::Error),
+    /// The Google Sheets response could not be parsed as JSON.
+    Json(serde_json::Error),
+}
+
+impl GwasError {
+    /// The process exit code to report for this error, so a workflow
+    /// manager driving many per-trait runs can tell failure classes apart
+    /// (retry, skip, or alert) without parsing log text.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GwasError::LegendError(_) => 2,
+            GwasError::MissingFile(_) => 3,
+            GwasError::InputParseError { .. } => 4,
+            GwasError::LiftoverError(_) => 5,
+            GwasError::FastaError(_) => 6,
+            GwasError::ThreadPoolError(_) => 7,
+            GwasError::EmptyResult(_) => 8,
+            GwasError::ExternalToolError { .. }
+            | GwasError::Io(_)
+            | GwasError::Http(_)
+            | GwasError::Json(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for GwasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GwasError::LegendError(msg) => write!(f, "legend error: {msg}"),
+            GwasError::InputParseError { line, col, message } => {
+                write!(
+                    f,
+                    "input parse error at line {line}, column {col}: {message}"
+                )
+            },
+            GwasError::LiftoverError(msg) => write!(f, "liftover error: {msg}"),
+            GwasError::FastaError(msg) => write!(f, "FASTA error: {msg}"),
+            GwasError::MissingFile(msg) => write!(f, "missing file: {msg}"),
+            GwasError::EmptyResult(msg) => write!(f, "empty result: {msg}"),
+            GwasError::ExternalToolError { tool, message } => {
+                write!(f, "external tool `{tool}` failed: {message}")
+            },
+            GwasError::ThreadPoolError(msg) => write!(f, "thread pool error: {msg}"),
+            GwasError::Io(e) => write!(f, "I/O error: {e}"),
+            GwasError::Http(e) => write!(f, "HTTP error: {e}"),
+            GwasError::Json(e) => write!(f, "JSON error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for GwasError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GwasError::Io(e) => Some(e),
+            GwasError::Http(e) => Some(e),
+            GwasError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for GwasError {
+    fn from(e: std::io::Error) -> Self {
+        GwasError::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for GwasError {
+    fn from(e: reqwest::Error) -> Self {
+        GwasError::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for GwasError {
+    fn from(e: serde_json::Error) -> Self {
+        GwasError::Json(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, GwasError>;
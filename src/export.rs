@@ -0,0 +1,246 @@
+//! Converts an already-harmonized output table into the column layouts
+//! downstream GWAS tools expect, without re-running any harmonization
+//! stage. Driven by the standalone `convert` subcommand.
+
+use std::{io::Write, path::Path};
+
+use crate::{
+    error::{GwasError, Result},
+    field::Field,
+    Data,
+};
+
+/// Which coordinate columns (`chr_hg19`/`pos_hg19` or `chr_hg38`/`pos_hg38`)
+/// to read out of an already-harmonized [`Data`] table, for formats that
+/// only have room for a single coordinate system.
+#[derive(Clone, Debug, Default, clap::ValueEnum)]
+pub enum GenomeBuild {
+    #[default]
+    Hg19,
+    Hg38,
+}
+
+impl GenomeBuild {
+    fn chr_col(&self) -> &'static str {
+        match self {
+            GenomeBuild::Hg19 => "chr_hg19",
+            GenomeBuild::Hg38 => "chr_hg38",
+        }
+    }
+
+    fn pos_col(&self) -> &'static str {
+        match self {
+            GenomeBuild::Hg19 => "pos_hg19",
+            GenomeBuild::Hg38 => "pos_hg38",
+        }
+    }
+
+    /// The build name as it appears in this crate's column names (`chr_hg19`,
+    /// `pos_hg38`, ...), for callers outside this module building a column
+    /// name of their own (e.g. [`crate::dbsnp_vcf::read_dbsnp_vcf`]).
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            GenomeBuild::Hg19 => "hg19",
+            GenomeBuild::Hg38 => "hg38",
+        }
+    }
+}
+
+/// An export format the `convert` subcommand can write an already-harmonized
+/// table out as.
+#[derive(Clone, Debug, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// A minimal GWAS-VCF with `ES`/`SE`/`LP`/`AF`/`SS` INFO fields.
+    GwasVcf,
+    /// The column set `munge_sumstats.py` expects: SNP, A1, A2, N, Z, P.
+    Ldsc,
+    /// The `.ma` column set GCTA-COJO expects: SNP, A1, A2, freq, b, se, p,
+    /// N.
+    Cojo,
+    /// The column set PRS-CS expects: SNP, A1, A2, BETA, P.
+    PrsCs,
+    /// Columnar Parquet, via [`Data::to_polars`]. Requires the `polars`
+    /// feature.
+    Parquet,
+}
+
+fn parse_f64(data: &Data, row: &[Field], col: &str) -> Result<f64> {
+    let raw = data.get_from_row(row, col);
+    raw.parse().map_err(|_| {
+        GwasError::InputParseError {
+            line:    0,
+            col:     data.idx(col),
+            message: format!("expected a number in column `{col}`, got `{raw}`"),
+        }
+    })
+}
+
+fn write_delimited(
+    data: &Data,
+    output: &Path,
+    header: &[&str],
+    row_fn: impl Fn(&[Field]) -> Result<Vec<String>>,
+) -> Result<()> {
+    let mut file = std::fs::File::create(output)?;
+    writeln!(file, "{}", header.join("\t"))?;
+    for r in &data.data {
+        writeln!(file, "{}", row_fn(r)?.join("\t"))?;
+    }
+    Ok(())
+}
+
+/// `N_eff` (effective sample size, see `N_eff` in `preformat`) when the row
+/// has one, since LDSC/COJO expect effective rather than total N for
+/// case-control studies -- falling back to `N_total` for quantitative
+/// traits, where `N_eff` is `NA`.
+fn n_for_export(data: &Data, r: &[Field]) -> String {
+    let n_eff = data.get_from_row(r, "N_eff");
+    if n_eff != "NA" {
+        n_eff.to_string()
+    } else {
+        data.get_from_row(r, "N_total").to_string()
+    }
+}
+
+fn write_ldsc(data: &Data, output: &Path) -> Result<()> {
+    write_delimited(data, output, &["SNP", "A1", "A2", "N", "Z", "P"], |r| {
+        let effect_size = parse_f64(data, r, "effect_size")?;
+        let se = parse_f64(data, r, "standard_error")?;
+        Ok(vec![
+            data.get_from_row(r, "rsid").to_string(),
+            data.get_from_row(r, "alt").to_string(),
+            data.get_from_row(r, "ref").to_string(),
+            n_for_export(data, r),
+            (effect_size / se).to_string(),
+            data.get_from_row(r, "pvalue").to_string(),
+        ])
+    })
+}
+
+fn write_cojo(data: &Data, output: &Path) -> Result<()> {
+    write_delimited(
+        data,
+        output,
+        &["SNP", "A1", "A2", "freq", "b", "se", "p", "N"],
+        |r| {
+            Ok(vec![
+                data.get_from_row(r, "rsid").to_string(),
+                data.get_from_row(r, "alt").to_string(),
+                data.get_from_row(r, "ref").to_string(),
+                data.get_from_row(r, "EAF").to_string(),
+                data.get_from_row(r, "effect_size").to_string(),
+                data.get_from_row(r, "standard_error").to_string(),
+                data.get_from_row(r, "pvalue").to_string(),
+                n_for_export(data, r),
+            ])
+        },
+    )
+}
+
+fn write_prs_cs(data: &Data, output: &Path) -> Result<()> {
+    write_delimited(data, output, &["SNP", "A1", "A2", "BETA", "P"], |r| {
+        Ok(vec![
+            data.get_from_row(r, "rsid").to_string(),
+            data.get_from_row(r, "alt").to_string(),
+            data.get_from_row(r, "ref").to_string(),
+            data.get_from_row(r, "effect_size").to_string(),
+            data.get_from_row(r, "pvalue").to_string(),
+        ])
+    })
+}
+
+fn write_gwas_vcf(data: &Data, build: &GenomeBuild, output: &Path) -> Result<()> {
+    let mut file = std::fs::File::create(output)?;
+    writeln!(file, "##fileformat=VCFv4.2")?;
+    writeln!(
+        file,
+        "##INFO=<ID=ES,Number=A,Type=Float,Description=\"Effect size estimate\">"
+    )?;
+    writeln!(
+        file,
+        "##INFO=<ID=SE,Number=A,Type=Float,Description=\"Standard error of effect size\">"
+    )?;
+    writeln!(
+        file,
+        "##INFO=<ID=LP,Number=A,Type=Float,Description=\"-log10 p-value\">"
+    )?;
+    writeln!(
+        file,
+        "##INFO=<ID=AF,Number=A,Type=Float,Description=\"Effect allele frequency\">"
+    )?;
+    writeln!(
+        file,
+        "##INFO=<ID=SS,Number=A,Type=Float,Description=\"Sample size\">"
+    )?;
+    writeln!(file, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+    let chr_col = build.chr_col();
+    let pos_col = build.pos_col();
+    for r in &data.data {
+        let pvalue = parse_f64(data, r, "pvalue")?;
+        let lp = if pvalue > 0.0 {
+            -pvalue.log10()
+        } else {
+            f64::INFINITY
+        };
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t.\t.\tES={};SE={};LP={lp};AF={};SS={}",
+            data.get_from_row(r, chr_col),
+            data.get_from_row(r, pos_col),
+            data.get_from_row(r, "rsid"),
+            data.get_from_row(r, "ref"),
+            data.get_from_row(r, "alt"),
+            data.get_from_row(r, "effect_size"),
+            data.get_from_row(r, "standard_error"),
+            data.get_from_row(r, "EAF"),
+            data.get_from_row(r, "N_total"),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "polars")]
+fn write_parquet(data: &Data, output: &Path) -> Result<()> {
+    use polars::prelude::*;
+    let mut df = data.to_polars().map_err(|e| {
+        GwasError::ExternalToolError {
+            tool:    "polars".to_string(),
+            message: e.to_string(),
+        }
+    })?;
+    let file = std::fs::File::create(output)?;
+    ParquetWriter::new(file).finish(&mut df).map_err(|e| {
+        GwasError::ExternalToolError {
+            tool:    "polars".to_string(),
+            message: e.to_string(),
+        }
+    })?;
+    Ok(())
+}
+
+#[cfg(not(feature = "polars"))]
+fn write_parquet(_data: &Data, _output: &Path) -> Result<()> {
+    Err(GwasError::ExternalToolError {
+        tool:    "parquet export".to_string(),
+        message: "this build was compiled without the `polars` feature; rebuild with `--features \
+                  polars` to export Parquet"
+            .to_string(),
+    })
+}
+
+/// Write `data` out to `output` in `format`, reading coordinates from
+/// `build` for formats that need a single coordinate system.
+pub fn convert(
+    data: &Data,
+    format: &ExportFormat,
+    build: &GenomeBuild,
+    output: &Path,
+) -> Result<()> {
+    match format {
+        ExportFormat::Ldsc => write_ldsc(data, output),
+        ExportFormat::Cojo => write_cojo(data, output),
+        ExportFormat::PrsCs => write_prs_cs(data, output),
+        ExportFormat::GwasVcf => write_gwas_vcf(data, build, output),
+        ExportFormat::Parquet => write_parquet(data, output),
+    }
+}
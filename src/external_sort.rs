@@ -0,0 +1,187 @@
+//! A disk-backed external sort (sorted runs + k-way merge) for row sets too
+//! large to comfortably hold a second, sorted copy of in memory --
+//! multi-ancestry meta-analyses can run to 300M rows, at which point
+//! `Vec::sort_by` over the whole table competes with everything else the
+//! pipeline is already holding resident. [`ExternalSortedRows::new`] spills
+//! fixed-size chunks to disk pre-sorted, then streams them back out in
+//! ascending order via a [`BinaryHeap`]-based merge, so only one run's worth
+//! of rows plus one buffered line per run is ever resident at once.
+//!
+//! Used by [`crate::dbsnp_matching_streaming`], whose merge-join against the
+//! dbSNP resource only ever needs the *next* raw row in `(chr, pos)` order,
+//! not the whole sorted table at once.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+use rayon::prelude::*;
+use tempfile::TempDir;
+
+use crate::{
+    error::{GwasError, Result},
+    field::Field,
+    split_fields,
+};
+
+/// Rows per sorted run spilled to disk before the k-way merge. Chosen so a
+/// single run (plus its sort) comfortably fits in memory regardless of how
+/// large the full row set is, the same "small enough, not tuned to the
+/// byte" rationale as the fixed fallback in [`crate::resolve_chunk_rows`].
+const EXTERNAL_SORT_RUN_ROWS: usize = 2_000_000;
+
+/// One run's next unread row, kept in the merge [`BinaryHeap`] alongside the
+/// `(chr, pos)` key it was read with so the heap doesn't need to re-parse
+/// the row to compare entries.
+struct HeapEntry {
+    chr:      String,
+    pos:      i64,
+    run:      usize,
+    row_line: String,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        (self.chr.as_str(), self.pos) == (other.chr.as_str(), other.pos)
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.chr.as_str(), self.pos).cmp(&(other.chr.as_str(), other.pos))
+    }
+}
+
+fn corrupt_run_error() -> GwasError {
+    GwasError::InputParseError {
+        line:    0,
+        col:     0,
+        message: "external sort run file is missing an expected column -- this is an internal \
+                  bug, not a problem with the input"
+            .to_string(),
+    }
+}
+
+/// Streams rows back out sorted ascending by `(chr, pos)`, via the external
+/// sort documented at the top of this module. Keeps its run files (and the
+/// temporary directory holding them) alive for as long as this is, so
+/// dropping it early -- e.g. a caller that breaks out of iteration --
+/// cleans them up along with it.
+pub(crate) struct ExternalSortedRows {
+    _tmp_dir: TempDir,
+    runs:     Vec<BufReader<std::fs::File>>,
+    heap:     BinaryHeap<Reverse<HeapEntry>>,
+    chr_idx:  usize,
+    pos_idx:  usize,
+}
+
+impl ExternalSortedRows {
+    /// Splits `rows` into [`EXTERNAL_SORT_RUN_ROWS`]-sized runs, sorts each
+    /// run in memory by `(row[chr_idx], row[pos_idx].parse::<i64>())`, and
+    /// spills it tab-delimited to its own file in a fresh temporary
+    /// directory, before returning a handle that merges the runs back out
+    /// lazily as it's iterated.
+    pub(crate) fn new(rows: Vec<Vec<Field>>, chr_idx: usize, pos_idx: usize) -> Result<Self> {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("gwas-summary-stats-sort-")
+            .tempdir()?;
+
+        let mut run_paths = Vec::with_capacity(rows.len().div_ceil(EXTERNAL_SORT_RUN_ROWS).max(1));
+        for (i, run) in rows.chunks(EXTERNAL_SORT_RUN_ROWS).enumerate() {
+            let mut run: Vec<&Vec<Field>> = run.iter().collect();
+            run.par_sort_by(|a, b| {
+                let a_pos = a[pos_idx].parse::<i64>().unwrap_or(i64::MAX);
+                let b_pos = b[pos_idx].parse::<i64>().unwrap_or(i64::MAX);
+                (a[chr_idx].as_str(), a_pos).cmp(&(b[chr_idx].as_str(), b_pos))
+            });
+            let run_path = tmp_dir.path().join(format!("run-{i}.tsv"));
+            let mut writer = BufWriter::new(std::fs::File::create(&run_path)?);
+            for row in run {
+                writer.write_all(row.join("\t").as_bytes())?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+            run_paths.push(run_path);
+        }
+        drop(rows);
+
+        let mut runs = Vec::with_capacity(run_paths.len());
+        let mut heap = BinaryHeap::with_capacity(run_paths.len());
+        for (i, run_path) in run_paths.into_iter().enumerate() {
+            let mut reader = BufReader::new(std::fs::File::open(&run_path)?);
+            if let Some(entry) = Self::read_entry(&mut reader, i, chr_idx, pos_idx)? {
+                heap.push(Reverse(entry));
+            }
+            runs.push(reader);
+        }
+
+        Ok(Self {
+            _tmp_dir: tmp_dir,
+            runs,
+            heap,
+            chr_idx,
+            pos_idx,
+        })
+    }
+
+    fn read_entry(
+        reader: &mut BufReader<std::fs::File>,
+        run: usize,
+        chr_idx: usize,
+        pos_idx: usize,
+    ) -> Result<Option<HeapEntry>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\n', '\r']).to_string();
+        let fields: Vec<&str> = split_fields(&line, b'\t').collect();
+        let chr = fields
+            .get(chr_idx)
+            .ok_or_else(corrupt_run_error)?
+            .to_string();
+        let pos = fields
+            .get(pos_idx)
+            .ok_or_else(corrupt_run_error)?
+            .parse::<i64>()
+            .unwrap_or(i64::MAX);
+        Ok(Some(HeapEntry {
+            chr,
+            pos,
+            run,
+            row_line: line,
+        }))
+    }
+}
+
+impl Iterator for ExternalSortedRows {
+    type Item = Result<Vec<Field>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(entry) = self.heap.pop()?;
+        let row: Vec<Field> = split_fields(&entry.row_line, b'\t')
+            .map(Field::from)
+            .collect();
+        match Self::read_entry(
+            &mut self.runs[entry.run],
+            entry.run,
+            self.chr_idx,
+            self.pos_idx,
+        ) {
+            Ok(Some(next_entry)) => self.heap.push(Reverse(next_entry)),
+            Ok(None) => {},
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok(row))
+    }
+}
@@ -0,0 +1,149 @@
+//! A row cell that can still point into the buffer it was parsed from,
+//! instead of every field copying its bytes into its own `String` up front.
+//! [`Data::parse`](crate::Data::parse) hands out [`Field::Borrowed`] cells
+//! for everything it reads, and a stage that needs to mutate a cell (e.g.
+//! [`crate::ref_alt_check`] flipping `ref`/`alt`) simply overwrites it with a
+//! [`Field::Owned`] one.
+
+use std::{
+    borrow::Borrow,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+/// A single `Data` row cell.
+#[derive(Clone, Debug)]
+pub enum Field {
+    /// A slice of a buffer kept alive by the shared `Arc`, e.g. the
+    /// decompressed input text `Data::parse` was called with.
+    Borrowed {
+        buf:   Arc<str>,
+        start: usize,
+        end:   usize,
+    },
+    /// A value that was constructed after parsing (a mutated cell, or a
+    /// column appended by a later stage) and so owns its own bytes.
+    Owned(String),
+}
+
+impl Field {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Field::Borrowed { buf, start, end } => &buf[*start..*end],
+            Field::Owned(s) => s.as_str(),
+        }
+    }
+}
+
+impl Deref for Field {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Field {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Field {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Field::Owned(String::new())
+    }
+}
+
+impl PartialEq for Field {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Field {}
+
+impl Hash for Field {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialEq<str> for Field {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Field {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Field {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<Field> for str {
+    fn eq(&self, other: &Field) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Field> for &str {
+    fn eq(&self, other: &Field) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl From<String> for Field {
+    fn from(s: String) -> Self {
+        Field::Owned(s)
+    }
+}
+
+impl From<&str> for Field {
+    fn from(s: &str) -> Self {
+        Field::Owned(s.to_string())
+    }
+}
+
+impl From<Field> for String {
+    fn from(f: Field) -> Self {
+        match f {
+            Field::Borrowed { .. } => f.as_str().to_string(),
+            Field::Owned(s) => s,
+        }
+    }
+}
+
+impl serde::Serialize for Field {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de> {
+        String::deserialize(deserializer).map(Field::Owned)
+    }
+}
@@ -0,0 +1,74 @@
+//! A string interner with pointer-identity `Hash`/`Eq`, for columns like the
+//! dbSNP resource's `chr`/`ref`/`alt` that repeat a tiny set of distinct
+//! values tens of millions of times over. Two [`Interned`] handles for equal
+//! strings always share the same heap allocation, so comparing or hashing
+//! them is pointer-sized work instead of a byte-wise string compare/hash --
+//! the win [`crate::dbsnp_matching`] wants for its dbSNP `HashMap` keys.
+
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// A handle into an [`Interner`]'s pool. Cheap to clone (an `Arc` bump), and
+/// compares/hashes by the pooled allocation's address rather than its
+/// contents, since every handle for a given value comes from the same
+/// `Interner` and therefore shares one allocation.
+#[derive(Clone, Debug)]
+pub(crate) struct Interned(Arc<str>);
+
+impl std::ops::Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Interned {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Interned {}
+
+impl Hash for Interned {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+/// Hands out the same [`Interned`] handle for equal strings, so a column
+/// with only a handful of distinct values ends up with one heap allocation
+/// per distinct value rather than one per row.
+///
+/// Matching dbSNP's interned columns against another source's (e.g. the
+/// harmonized input's `chr`/`ref`/`alt`) only works correctly when both
+/// sides intern through the *same* `Interner` -- see [`Interner::get`].
+#[derive(Default)]
+pub(crate) struct Interner {
+    pool: HashMap<Box<str>, Interned, ahash::RandomState>,
+}
+
+impl Interner {
+    /// Returns the pooled handle for `value`, allocating a new pool entry
+    /// the first time `value` is seen.
+    pub(crate) fn intern(&mut self, value: &str) -> Interned {
+        if let Some(existing) = self.pool.get(value) {
+            return existing.clone();
+        }
+        let interned = Interned(Arc::from(value));
+        self.pool.insert(value.into(), interned.clone());
+        interned
+    }
+
+    /// Returns the pooled handle for `value` without interning it, so a
+    /// value never seen on the interning side (and therefore never a valid
+    /// match) short-circuits instead of silently interning into a handle
+    /// that can't equal anything already in the pool.
+    pub(crate) fn get(&self, value: &str) -> Option<Interned> {
+        self.pool.get(value).cloned()
+    }
+}
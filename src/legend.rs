@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::{
+    error::{GwasError, Result},
+    field::Field,
+    Data,
+    GOOGLE_SHEETS_API_KEY,
+};
+
+/// Maximum number of attempts [`fetch_with_retry`] makes before giving up,
+/// including the first.
+const SHEETS_FETCH_MAX_ATTEMPTS: u32 = 5;
+/// Delay [`fetch_with_retry`] backs off by before each retry, doubled after
+/// every attempt (500ms, 1s, 2s, 4s, ...).
+const SHEETS_FETCH_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Narrows one row of a Google Sheets `values` response to its expected
+/// array shape, for [`GoogleSheetsSource::fetch`] to iterate its cells
+/// without a bare `.unwrap()` on a response shape it doesn't control.
+fn sheets_row_as_array(row: &serde_json::Value) -> Result<&Vec<serde_json::Value>> {
+    row.as_array().ok_or_else(|| {
+        GwasError::LegendError(format!(
+            "Google Sheets response didn't have the expected shape (expected every row in \
+             `values` to be an array): {row}"
+        ))
+    })
+}
+
+/// Fetches `url`, retrying with exponential backoff on transient failures
+/// (429 rate limiting, 5xx server errors, or a transport-level error) up to
+/// [`SHEETS_FETCH_MAX_ATTEMPTS`] times before giving up. Any other HTTP
+/// status fails immediately, since those aren't expected to resolve by
+/// retrying. Either way, a final failure's error includes the HTTP status
+/// and response body instead of just the bare `error_for_status` message.
+fn fetch_with_retry(url: &str) -> Result<String> {
+    let mut delay = SHEETS_FETCH_BASE_DELAY;
+    for attempt in 1..=SHEETS_FETCH_MAX_ATTEMPTS {
+        let last_attempt = attempt == SHEETS_FETCH_MAX_ATTEMPTS;
+        let response = match reqwest::blocking::get(url) {
+            Ok(response) => response,
+            Err(e) if !last_attempt => {
+                warn!(attempt, error = %e, "Google Sheets request failed, retrying");
+                std::thread::sleep(delay);
+                delay *= 2;
+                continue;
+            },
+            Err(e) => return Err(e.into()),
+        };
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.text()?);
+        }
+        let body = response.text().unwrap_or_default();
+        if (status.as_u16() == 429 || status.is_server_error()) && !last_attempt {
+            warn!(attempt, %status, "Google Sheets request failed, retrying");
+            std::thread::sleep(delay);
+            delay *= 2;
+            continue;
+        }
+        return Err(GwasError::LegendError(format!(
+            "Google Sheets request to {url} failed with status {status}: {body}"
+        )));
+    }
+    unreachable!("the loop above always returns on its last attempt")
+}
+
+/// Somewhere the GWAS formatting legend (one row per trait) can be fetched
+/// from. Keeping the pipeline behind this trait means the Google Sheet can
+/// be swapped for a local file or an institutional database without
+/// touching `preformat` or anything downstream, since they only ever see
+/// the resulting [`Data`].
+pub trait LegendSource {
+    fn fetch(&self) -> Result<Data>;
+}
+
+/// The original legend source: a published Google Sheet, fetched through
+/// the Sheets API.
+pub struct GoogleSheetsSource {
+    pub spreadsheet_id: String,
+}
+
+impl LegendSource for GoogleSheetsSource {
+    fn fetch(&self) -> Result<Data> {
+        let malformed_response = |context: &str, body: &serde_json::Value| {
+            GwasError::LegendError(format!(
+                "Google Sheets response for spreadsheet `{}` didn't have the expected shape \
+                 ({context}): {body}",
+                self.spreadsheet_id
+            ))
+        };
+        let spreadsheet = fetch_with_retry(&format!(
+            "https://sheets.googleapis.com/v4/spreadsheets/{}?key={}",
+            self.spreadsheet_id, GOOGLE_SHEETS_API_KEY
+        ))?;
+        let spreadsheet: serde_json::Value = serde_json::from_str(&spreadsheet)?;
+        let sheet_title = spreadsheet["sheets"]
+            .as_array()
+            .and_then(|sheets| sheets.first())
+            .and_then(|sheet| sheet["properties"]["title"].as_str())
+            .ok_or_else(|| {
+                malformed_response("expected `sheets[0].properties.title`", &spreadsheet)
+            })?;
+        let data = fetch_with_retry(&format!(
+            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?key={}",
+            self.spreadsheet_id, sheet_title, GOOGLE_SHEETS_API_KEY
+        ))?;
+        let data: serde_json::Value = serde_json::from_str(&data)?;
+        let cell_as_str = |cell: &serde_json::Value| {
+            cell.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| malformed_response("expected every cell to be a string", &data))
+        };
+        let rows = data["values"]
+            .as_array()
+            .ok_or_else(|| malformed_response("expected a `values` array", &data))?;
+        let header_row = rows.first().ok_or_else(|| {
+            malformed_response("expected at least a header row in `values`", &data)
+        })?;
+        let header = sheets_row_as_array(header_row)?
+            .iter()
+            .map(cell_as_str)
+            .collect::<Result<Vec<_>>>()?;
+        let data = rows[1..]
+            .iter()
+            .map(|row| {
+                sheets_row_as_array(row)?
+                    .iter()
+                    .map(|cell| cell_as_str(cell).map(Field::from))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Data::from_header_and_rows(header, data))
+    }
+}
+
+/// A legend kept as a local CSV/TSV file, for sites that maintain their
+/// trait metadata outside of Google Sheets.
+pub struct CsvLegendSource {
+    pub path:  std::path::PathBuf,
+    pub delim: char,
+}
+
+impl LegendSource for CsvLegendSource {
+    fn fetch(&self) -> Result<Data> {
+        let file = std::fs::File::open(&self.path)?;
+        Ok(Data::read(self.delim, file, true, None))
+    }
+}
+
+/// A legend stored in an institutional SQL database.
+///
+/// Not yet implemented: connecting to an arbitrary institutional database
+/// needs a driver decision (Postgres vs MySQL vs ODBC) that should be made
+/// with the teams that would actually use it. Wired up here so the CLI
+/// surface and `LegendSource` trait are already in place for that follow-up.
+pub struct SqlLegendSource {
+    pub connection_string: String,
+    pub query:             String,
+}
+
+impl LegendSource for SqlLegendSource {
+    fn fetch(&self) -> Result<Data> {
+        Err(GwasError::LegendError(format!(
+            "SQL legend sources are not yet implemented (query `{}` against `{}`)",
+            self.query, self.connection_string
+        )))
+    }
+}
@@ -0,0 +1,299 @@
+//! A pure-Rust reader for UCSC `.over.chain.gz` liftover chain files, used as
+//! the default way to move coordinates between genome builds instead of
+//! shelling out to the external `liftOver` binary (see
+//! [`crate::Args::liftover_tool`]). `liftOver` isn't packaged for
+//! ARM and is an extra binary every deployment has to source and license;
+//! chain files are plain data this crate can already read (gzip, the same
+//! as the dbSNP resource) and the mapping itself is sorted-interval lookup.
+//!
+//! This assumes the chain file is the standard UCSC "best chain" file -- one
+//! target interval maps to at most one query interval, true of every chain
+//! file this crate documents downloading -- and does not implement
+//! chain-priority/score resolution for overlapping chains.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use rayon::prelude::*;
+use tracing::debug;
+
+use crate::error::{GwasError, Result};
+
+fn chain_error(message: impl Into<String>) -> GwasError {
+    GwasError::LiftoverError(message.into())
+}
+
+/// One ungapped alignment block of a chain, in 0-based half-open target
+/// coordinates (matching this crate's BED convention), plus the query
+/// position `t_start` maps to, already converted to forward-strand
+/// coordinates.
+struct Block {
+    t_start:    u64,
+    t_end:      u64,
+    q_start:    u64,
+    q_negative: bool,
+}
+
+/// Every chain in one `.over.chain.gz` file, grouped by target chromosome
+/// and sorted by `t_start` so a lookup is a binary search rather than a
+/// linear scan.
+struct ChainMap {
+    by_chrom: std::collections::HashMap<String, (String, Vec<Block>)>,
+}
+
+impl ChainMap {
+    /// Parses every chain in `path` into a lookup table.
+    fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).map_err(|e| {
+            chain_error(format!("failed to open chain file {}: {e}", path.display()))
+        })?;
+        let reader = BufReader::new(flate2::read::GzDecoder::new(file));
+
+        let mut by_chrom: std::collections::HashMap<String, (String, Vec<Block>)> =
+            std::collections::HashMap::new();
+        // (target chromosome, query chromosome, query size, query strand is `-`)
+        let mut header: Option<(String, String, u64, bool)> = None;
+        let mut t_pos = 0u64;
+        let mut q_pos = 0u64;
+
+        for line in reader.lines() {
+            let line = line.map_err(|e| chain_error(format!("failed to read chain file: {e}")))?;
+            let line = line.trim();
+            if line.is_empty() {
+                header = None;
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("chain ") {
+                let fields: Vec<&str> = rest.split_whitespace().collect();
+                if fields.len() < 11 {
+                    return Err(chain_error(format!(
+                        "malformed chain header line: `{line}`"
+                    )));
+                }
+                if fields[3] == "-" {
+                    return Err(chain_error(
+                        "chain files with a `-` target strand are not supported".to_string(),
+                    ));
+                }
+                let t_name = fields[1].to_string();
+                let t_start: u64 = fields[4]
+                    .parse()
+                    .map_err(|_| chain_error(format!("bad tStart in `{line}`")))?;
+                let q_name = fields[6].to_string();
+                let q_size: u64 = fields[7]
+                    .parse()
+                    .map_err(|_| chain_error(format!("bad qSize in `{line}`")))?;
+                let q_negative = fields[8] == "-";
+                let q_start: u64 = fields[9]
+                    .parse()
+                    .map_err(|_| chain_error(format!("bad qStart in `{line}`")))?;
+                header = Some((t_name, q_name, q_size, q_negative));
+                t_pos = t_start;
+                q_pos = q_start;
+                continue;
+            }
+            let Some((t_name, q_name, q_size, q_negative)) = header.clone() else {
+                return Err(chain_error(format!(
+                    "alignment line outside of a chain: `{line}`"
+                )));
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let size: u64 = fields
+                .first()
+                .ok_or_else(|| chain_error(format!("empty alignment line: `{line}`")))?
+                .parse()
+                .map_err(|_| chain_error(format!("bad block size in `{line}`")))?;
+
+            let q_start_fwd = if q_negative {
+                q_size - q_pos - size
+            } else {
+                q_pos
+            };
+            by_chrom
+                .entry(t_name)
+                .or_insert_with(|| (q_name, Vec::new()))
+                .1
+                .push(Block {
+                    t_start: t_pos,
+                    t_end: t_pos + size,
+                    q_start: q_start_fwd,
+                    q_negative,
+                });
+
+            if fields.len() == 1 {
+                header = None;
+                continue;
+            }
+            if fields.len() < 3 {
+                return Err(chain_error(format!("malformed alignment line: `{line}`")));
+            }
+            let dt: u64 = fields[1]
+                .parse()
+                .map_err(|_| chain_error(format!("bad dt in `{line}`")))?;
+            let dq: u64 = fields[2]
+                .parse()
+                .map_err(|_| chain_error(format!("bad dq in `{line}`")))?;
+            t_pos += size + dt;
+            q_pos += size + dq;
+        }
+
+        for (_, blocks) in by_chrom.values_mut() {
+            blocks.sort_by_key(|b| b.t_start);
+        }
+
+        Ok(Self { by_chrom })
+    }
+
+    /// Maps a 0-based, half-open `[start, end)` target interval -- not just
+    /// its start -- to its query-side start position and whether the block
+    /// that mapped it runs on the query's minus strand. Requiring the whole
+    /// interval (not just `start`) to fall inside one aligned block is what
+    /// makes this safe to call with a multi-base indel's full span rather
+    /// than a single anchor base: a deletion whose first base maps cleanly
+    /// but whose remaining bases fall in a different block (or a gap)
+    /// otherwise gets silently anchored at the wrong target coordinate. The
+    /// minus-strand flag is this chain's own alignment only; a caller
+    /// chaining multiple hops (as [`crate::liftover_path`] does) needs to
+    /// XOR it with whatever strand the row already carried in.
+    fn lift(&self, chrom: &str, start: u64, end: u64) -> LiftOutcome<'_> {
+        let Some((q_name, blocks)) = self.by_chrom.get(chrom) else {
+            return LiftOutcome::UnknownChrom;
+        };
+        let i = blocks.partition_point(|b| b.t_end <= start);
+        let Some(block) = blocks.get(i) else {
+            return LiftOutcome::NotCovered;
+        };
+        if start < block.t_start {
+            return LiftOutcome::NotCovered;
+        }
+        if end > block.t_end {
+            return LiftOutcome::SpansGap;
+        }
+        let offset = start - block.t_start;
+        let q_pos = if block.q_negative {
+            block.q_start + (block.t_end - block.t_start - 1 - offset)
+        } else {
+            block.q_start + offset
+        };
+        LiftOutcome::Mapped(q_name.as_str(), q_pos, block.q_negative)
+    }
+}
+
+/// What [`ChainMap::lift`] found for one `[start, end)` target interval.
+enum LiftOutcome<'a> {
+    /// The whole interval falls inside one aligned block, mapped to the
+    /// query-side start position and strand of that block.
+    Mapped(&'a str, u64, bool),
+    /// No aligned block on this chromosome covers `start` at all -- the
+    /// region was deleted in the target assembly.
+    NotCovered,
+    /// `start` is covered, but the interval extends past that block's end --
+    /// the indel straddles an alignment boundary and can't be anchored
+    /// consistently.
+    SpansGap,
+    /// `chrom` has no chain entries at all.
+    UnknownChrom,
+}
+
+/// One bed row's outcome from [`native_liftover`]'s lookup, either the
+/// lifted row in the same BED6 format as the input, or the reason it didn't
+/// map paired with its original (unlifted) row -- the same shape `liftOver`'s
+/// own `unmapped_bed` comment convention carries.
+enum LiftResult {
+    Mapped(String),
+    Unmapped(&'static str, String),
+}
+
+/// Reads `input_bed` (BED6: chromosome, 0-based start, end -- spanning the
+/// full `ref` allele, not just its first base, so a multi-base indel isn't
+/// anchored on one base alone -- an embedded line number, a score column
+/// `liftOver` requires but this crate never reads, and a strand -- the
+/// format [`crate::format_bed_rows_parallel`] writes), maps each row's full
+/// `[start, end)` interval through `chain_path`, and writes the rows that
+/// mapped to `output_bed` in the same 6-column format, so callers don't
+/// need to know whether this or the external `liftOver` binary produced it.
+/// A row's output strand is its input strand XORed with whichever strand the
+/// chain block that mapped it runs on, so a minus-strand hop flips it and a
+/// second minus-strand hop flips it back -- the same accumulation `liftOver`
+/// itself does across chained BED6 lifts, letting
+/// [`crate::merge_liftover_bed_columns`] read a single cumulative flag off
+/// the final hop instead of re-deriving it from every intermediate one. Rows
+/// that fall outside every aligned block are written to `unmapped_bed`
+/// instead, each preceded by a `#`-prefixed reason line in the same
+/// convention `liftOver` uses for its own `unmapped_bed`, for
+/// [`crate::parse_unmapped_bed`] to read back.
+pub(crate) fn native_liftover(
+    chain_path: &Path,
+    input_bed: &Path,
+    output_bed: &Path,
+    unmapped_bed: &Path,
+) -> Result<()> {
+    let chain = ChainMap::load(chain_path)?;
+
+    let input = std::fs::read_to_string(input_bed)?;
+    let lines: Vec<&str> = input.lines().collect();
+    let results: Vec<LiftResult> = lines
+        .par_iter()
+        .map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let parsed = (|| -> Option<(&str, u64, u64, &str, &str)> {
+                Some((
+                    *fields.first()?,
+                    fields.get(1)?.parse::<u64>().ok()?,
+                    fields.get(2)?.parse::<u64>().ok()?,
+                    *fields.get(3)?,
+                    *fields.get(5)?,
+                ))
+            })();
+            let Some((chrom, start, end, name, strand)) = parsed else {
+                return LiftResult::Unmapped("malformed bed row", (*line).to_string());
+            };
+            match chain.lift(chrom, start, end) {
+                LiftOutcome::Mapped(q_chrom, q_start, q_negative) => {
+                    let q_end = q_start + (end - start);
+                    let out_strand = if (strand == "-") ^ q_negative {
+                        "-"
+                    } else {
+                        "+"
+                    };
+                    LiftResult::Mapped(format!(
+                        "{q_chrom}\t{q_start}\t{q_end}\t{name}\t0\t{out_strand}"
+                    ))
+                },
+                LiftOutcome::NotCovered => {
+                    LiftResult::Unmapped("Deleted in new", (*line).to_string())
+                },
+                LiftOutcome::SpansGap => LiftResult::Unmapped("Split in new", (*line).to_string()),
+                LiftOutcome::UnknownChrom => {
+                    LiftResult::Unmapped("Unknown chromosome", (*line).to_string())
+                },
+            }
+        })
+        .collect();
+
+    let mapped_count = results
+        .iter()
+        .filter(|r| matches!(r, LiftResult::Mapped(_)))
+        .count();
+    debug!(
+        total = lines.len(),
+        mapped = mapped_count,
+        unmapped = lines.len() - mapped_count,
+        "native liftover complete"
+    );
+
+    let mut out = std::io::BufWriter::new(std::fs::File::create(output_bed)?);
+    let mut unmapped = std::io::BufWriter::new(std::fs::File::create(unmapped_bed)?);
+    for result in results {
+        match result {
+            LiftResult::Mapped(line) => writeln!(out, "{line}")?,
+            LiftResult::Unmapped(reason, line) => {
+                writeln!(unmapped, "#{reason}")?;
+                writeln!(unmapped, "{line}")?;
+            },
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! Translates retired/merged rsIDs to their current IDs via NCBI's dbSNP
+//! merge history (`RsMergeArch`/`SNPHistory`), so a stale rsID baked into an
+//! older raw sumstats file, or carried by the dbSNP resource itself if it
+//! predates a later merge, doesn't silently fail to join against an LD
+//! reference panel that only indexes current IDs.
+//!
+//! Expects a simple two-column `old_rsid`/`current_rsid` TSV rather than
+//! NCBI's own `RsMergeArch`/`SNPHistory` release directly -- the same
+//! "bespoke preprocessed TSV" convention [`crate::dbsnp_vcf`] documents for
+//! the dbSNP resource itself -- since a raw `RsMergeArch` record only gives
+//! one merge hop (`rsHigh` retired into `rsLow`, which may itself have been
+//! merged again later) and resolving the full chain down to each retired
+//! ID's final current replacement is a one-time preprocessing step, not
+//! something every run should repeat.
+
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::{error::Result, field::Field, Data};
+
+/// A loaded rsID merge-history table: every retired rsID it tracks, mapped
+/// straight to its current replacement.
+pub(crate) struct RsMergeTable {
+    current: std::collections::HashMap<String, String, ahash::RandomState>,
+}
+
+impl RsMergeTable {
+    /// Reads a two-column (`old_rsid`, `current_rsid`) TSV, gzip-compressed
+    /// or plain, built ahead of time from NCBI's `RsMergeArch`/`SNPHistory`
+    /// release -- see the module docs for why this crate doesn't parse that
+    /// format directly.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let reader: Box<dyn std::io::Read> = if path.to_string_lossy().ends_with(".gz") {
+            Box::new(flate2::read::GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+        let data = Data::read('\t', reader, true, None);
+        let old_idx = data.idx("old_rsid");
+        let current_idx = data.idx("current_rsid");
+        let current = data
+            .data
+            .par_iter()
+            .map(|r| {
+                (
+                    r[old_idx].as_str().to_string(),
+                    r[current_idx].as_str().to_string(),
+                )
+            })
+            .collect();
+        Ok(Self { current })
+    }
+
+    /// Rewrites every value of `data`'s `column` that this table tracks as
+    /// retired to its current rsID, in place. Returns how many rows were
+    /// updated, for the caller to report.
+    pub(crate) fn update_column(&self, data: &mut Data, column: &str) -> usize {
+        let idx = data.idx(column);
+        let updated = std::sync::atomic::AtomicUsize::new(0);
+        data.data.par_iter_mut().for_each(|r| {
+            if let Some(current) = self.current.get(r[idx].as_str()) {
+                r[idx] = Field::from(current.clone());
+                updated.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        });
+        updated.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,201 @@
+use std::{collections::HashMap, path::Path};
+
+use rayon::prelude::*;
+use tracing::info;
+
+use crate::{
+    dbsnp_matching,
+    dbsnp_matching_chromosome_streamed,
+    dbsnp_matching_streaming,
+    dbsnp_vcf,
+    error::{GwasError, Result},
+    field::Field,
+    rs_merge::RsMergeTable,
+    Ctx,
+    Data,
+};
+
+/// Selects a [`VariantMatcher`] implementation from the CLI.
+#[derive(Clone, Debug, Default, clap::ValueEnum)]
+pub enum VariantMatcherKind {
+    /// Exact (chr, pos, ref, alt) matching with a ref/alt-flipped fallback.
+    #[default]
+    ExactFlipped,
+    /// Match purely on rsID.
+    Rsid,
+    /// Exact/flipped matching like `exact-flipped`, but reads the dbSNP
+    /// resource via per-chromosome tabix region queries instead of loading
+    /// it in full, so harmonizing a small targeted sumstats file (e.g.
+    /// Immunochip, Metabochip) only pays for the chromosomes it actually
+    /// covers. Requires a bgzip-compressed dbSNP file with a companion
+    /// `.tbi` index.
+    TabixRegion,
+    /// Exact/flipped matching like `exact-flipped`, but via a sorted
+    /// streaming merge-join against the dbSNP resource instead of an
+    /// in-memory `HashMap`, to stay within a small memory footprint on the
+    /// full dbSNP build.
+    StreamingSortedMerge,
+    /// Exact/flipped matching like `exact-flipped`, but builds its
+    /// `HashMap` one chromosome at a time by re-reading the dbSNP resource
+    /// once per chromosome present in the input, so peak memory is bounded
+    /// by the largest chromosome's share of the resource instead of the
+    /// whole thing -- without requiring the dbSNP resource to be sorted
+    /// the way `streaming-sorted-merge` does.
+    ChromosomeStreamed,
+}
+
+impl VariantMatcherKind {
+    pub fn build(&self) -> Box<dyn VariantMatcher> {
+        match self {
+            VariantMatcherKind::ExactFlipped => Box::new(ExactFlippedMatcher),
+            VariantMatcherKind::Rsid => Box::new(RsidMatcher),
+            VariantMatcherKind::TabixRegion => Box::new(TabixRegionMatcher),
+            VariantMatcherKind::StreamingSortedMerge => Box::new(StreamingSortedMergeMatcher),
+            VariantMatcherKind::ChromosomeStreamed => Box::new(ChromosomeStreamedMatcher),
+        }
+    }
+
+    /// Whether this matcher's join key against the dbSNP resource needs both
+    /// hg19 and hg38 coordinates to match at all, rather than just to display.
+    /// `exact-flipped`, `tabix-region`, `streaming-sorted-merge`, and
+    /// `chromosome-streamed` all key on `(chr_hg19, pos_hg19, ref, alt,
+    /// pos_hg38)`, so [`crate::liftover`] can't skip either build's
+    /// liftover pass for them no matter what [`crate::Args::output_builds`]
+    /// asks for; `rsid` pulls dbSNP's own coordinate columns straight off
+    /// its rsID index and never touches the liftover output, so it's the
+    /// only one a narrower `--builds` can actually speed up today.
+    pub fn needs_both_builds(&self) -> bool {
+        match self {
+            VariantMatcherKind::ExactFlipped
+            | VariantMatcherKind::TabixRegion
+            | VariantMatcherKind::StreamingSortedMerge
+            | VariantMatcherKind::ChromosomeStreamed => true,
+            VariantMatcherKind::Rsid => false,
+        }
+    }
+}
+
+/// A strategy for annotating harmonized variants against a reference
+/// resource and splitting them into matched and unmatched tables.
+///
+/// Abstracting this step lets alternative resources or join strategies be
+/// swapped in without touching `preformat` or `ref_alt_check`, which only
+/// care about the resulting `(matched, unmatched)` tables.
+pub trait VariantMatcher {
+    fn match_variants(&self, ctx: &Ctx, raw_data: Data) -> Result<(Data, Data)>;
+}
+
+/// The original strategy: exact (chr, pos, ref, alt) matching against the
+/// dbSNP resource, falling back to a ref/alt-swapped ("flipped") match.
+pub struct ExactFlippedMatcher;
+
+impl VariantMatcher for ExactFlippedMatcher {
+    fn match_variants(&self, ctx: &Ctx, raw_data: Data) -> Result<(Data, Data)> {
+        dbsnp_matching(ctx, raw_data)
+    }
+}
+
+/// Matches purely on rsID, ignoring position and alleles entirely. Useful
+/// for genotyping arrays where the rsID is trusted more than the
+/// liftover-derived coordinates. The only matcher that can read the official
+/// dbSNP VCF release directly instead of this crate's bespoke preprocessed
+/// TSV (see [`dbsnp_vcf`]), since its join key is the rsID alone.
+pub struct RsidMatcher;
+
+impl VariantMatcher for RsidMatcher {
+    fn match_variants(&self, ctx: &Ctx, mut raw_data: Data) -> Result<(Data, Data)> {
+        let mut dbsnp = if dbsnp_vcf::is_dbsnp_vcf(&ctx.args.dbsnp_file) {
+            let build = ctx.args.dbsnp_vcf_build.as_ref().ok_or_else(|| {
+                GwasError::LegendError(
+                    "--dbsnp-file looks like the official dbSNP VCF release; pass \
+                     --dbsnp-vcf-build to say which genome build its positions are on"
+                        .to_string(),
+                )
+            })?;
+            dbsnp_vcf::read_dbsnp_vcf(Path::new(&ctx.args.dbsnp_file), build)?
+        } else {
+            let dbsnp = flate2::read::GzDecoder::new(std::fs::File::open(&ctx.args.dbsnp_file)?);
+            Data::read('\t', dbsnp, true, None)
+        };
+        if let Some(rs_merge_file) = &ctx.args.rs_merge_file {
+            let rs_merge = RsMergeTable::load(Path::new(rs_merge_file))?;
+            let updated_dbsnp = rs_merge.update_column(&mut dbsnp, "rsid");
+            let updated_raw = rs_merge.update_column(&mut raw_data, "rsid");
+            info!(
+                updated_dbsnp,
+                updated_raw, "Translated retired rsIDs to their current ID"
+            );
+        }
+        let dbsnp_rsid = dbsnp.idx("rsid");
+        // ahash instead of the default SipHash, same rationale as
+        // `dbsnp_matching`'s join map: probed once per raw input row, and
+        // DoS resistance doesn't matter for keys we generated ourselves.
+        let dbsnp_map: HashMap<&str, &Vec<Field>, ahash::RandomState> =
+            HashMap::from_par_iter(dbsnp.data.par_iter().map(|r| (r[dbsnp_rsid].as_str(), r)));
+        let raw_rsid = raw_data.idx("rsid");
+        let header_len = raw_data.header.len();
+        let mut matched_header = raw_data.header.clone();
+        for (i, h) in dbsnp.header.iter().enumerate() {
+            if i != dbsnp_rsid {
+                matched_header.push(h.clone());
+            }
+        }
+        let data = std::mem::take(&mut raw_data.data);
+        let (matched, missing): (Vec<_>, Vec<_>) = data.into_par_iter().partition_map(|r| {
+            match dbsnp_map.get(r[raw_rsid].as_str()) {
+                Some(dbsnp_row) => {
+                    let mut r = r;
+                    for (i, v) in dbsnp_row.iter().enumerate() {
+                        if i != dbsnp_rsid {
+                            r.push(v.clone());
+                        }
+                    }
+                    itertools::Either::Left(r)
+                },
+                None => itertools::Either::Right(r),
+            }
+        });
+        debug_assert!(matched
+            .iter()
+            .all(|r| r.len() == header_len + dbsnp.header.len() - 1));
+        Ok((
+            Data::from_header_and_rows(matched_header, matched),
+            Data::from_header_and_rows(raw_data.header, missing),
+        ))
+    }
+}
+
+/// Exact/flipped matching via a sorted streaming merge-join, so memory stays
+/// bounded by the number of alleles reported at a single position instead
+/// of the size of the whole dbSNP resource. Requires the dbSNP resource to
+/// be sorted by `(chr, pos_hg19)`.
+pub struct StreamingSortedMergeMatcher;
+
+impl VariantMatcher for StreamingSortedMergeMatcher {
+    fn match_variants(&self, ctx: &Ctx, raw_data: Data) -> Result<(Data, Data)> {
+        dbsnp_matching_streaming(ctx, raw_data)
+    }
+}
+
+/// Exact/flipped matching like [`ExactFlippedMatcher`], but sources dbSNP
+/// candidate rows via per-chromosome tabix region queries (see
+/// [`crate::dbsnp_tabix`]) instead of reading the whole resource into an
+/// in-memory `HashMap`.
+pub struct TabixRegionMatcher;
+
+impl VariantMatcher for TabixRegionMatcher {
+    fn match_variants(&self, ctx: &Ctx, raw_data: Data) -> Result<(Data, Data)> {
+        dbsnp_matching(ctx, raw_data)
+    }
+}
+
+/// Exact/flipped matching like [`ExactFlippedMatcher`], but builds its join
+/// `HashMap` one chromosome at a time instead of over the whole dbSNP
+/// resource (see [`dbsnp_matching_chromosome_streamed`]).
+pub struct ChromosomeStreamedMatcher;
+
+impl VariantMatcher for ChromosomeStreamedMatcher {
+    fn match_variants(&self, ctx: &Ctx, raw_data: Data) -> Result<(Data, Data)> {
+        dbsnp_matching_chromosome_streamed(ctx, raw_data)
+    }
+}